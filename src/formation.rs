@@ -0,0 +1,231 @@
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ggrs::{Rollback, RollbackIdProvider};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::health::{Damage, Health};
+use crate::netplay::{GameRng, FIXED_DELTA};
+use crate::settings::Settings;
+use crate::{
+    Enemy, EnemyBullet, EnemyFireSound, ENEMY_BULLET_DAMAGE, ENEMY_BULLET_SIZE,
+    ENEMY_BULLET_SPEED, ENEMY_FIRE_TIME_SEC, ENEMY_MAX_HEALTH, ENEMY_PER_ROW, ENEMY_SIZE,
+    ENEMY_SPEED, GAMEAREA_PADDING,
+};
+
+const FORMATION_MEMBER_MAX: usize = 2;
+const FORMATION_BASE_SPEED: f32 = 0.6;
+
+#[derive(Clone, Copy, Debug)]
+struct FormationTemplate {
+    start: Vec2,
+    radius: Vec2,
+    pivot: Vec2,
+    speed: f32,
+}
+
+#[derive(Resource, Default, Clone)]
+pub struct FormationMaker {
+    current_template: Option<FormationTemplate>,
+    current_member_count: usize,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Formation {
+    pub start: Vec2,
+    pub radius: Vec2,
+    pub pivot: Vec2,
+    pub speed: f32,
+    pub angle: f32,
+}
+
+impl FormationMaker {
+    // `rng` is threaded in rather than pulled from `rand::thread_rng()` so that,
+    // under a GGRS rollback session, both peers generate identical formations
+    // from the shared, seeded `GameRng` resource.
+    pub fn make(&mut self, window: &Window, rng: &mut impl Rng) -> Formation {
+        let template = match self.current_template {
+            Some(template) if self.current_member_count < FORMATION_MEMBER_MAX => template,
+            _ => {
+                let template = Self::random_template(window, rng);
+                self.current_template = Some(template);
+                self.current_member_count = 0;
+                template
+            }
+        };
+
+        self.current_member_count += 1;
+
+        let offset = Vec2::new(rng.gen_range(-40.0..40.0), rng.gen_range(-40.0..40.0));
+
+        Formation {
+            start: template.start + offset,
+            radius: template.radius,
+            pivot: template.pivot,
+            speed: template.speed,
+            angle: rng.gen_range(0.0..TAU),
+        }
+    }
+
+    fn random_template(window: &Window, rng: &mut impl Rng) -> FormationTemplate {
+        let x_min = GAMEAREA_PADDING;
+        let x_max = window.width() - GAMEAREA_PADDING;
+        let y_min = GAMEAREA_PADDING;
+        let y_max = window.height() - GAMEAREA_PADDING;
+
+        let pivot = Vec2::new(rng.gen_range(x_min..x_max), rng.gen_range(y_min..y_max));
+        let radius = Vec2::new(rng.gen_range(60.0..160.0), rng.gen_range(60.0..160.0));
+        let speed = FORMATION_BASE_SPEED * rng.gen_range(0.5..1.5);
+
+        FormationTemplate {
+            start: Vec2::new(pivot.x, window.height() + ENEMY_SIZE),
+            radius,
+            pivot,
+            speed,
+        }
+    }
+}
+
+/// Per-enemy cooldown between return-fire shots.
+#[derive(Component, Clone)]
+pub struct EnemyFireTimer {
+    pub timer: Timer,
+}
+
+impl Default for EnemyFireTimer {
+    fn default() -> EnemyFireTimer {
+        EnemyFireTimer {
+            timer: Timer::from_seconds(ENEMY_FIRE_TIME_SEC, TimerMode::Repeating),
+        }
+    }
+}
+
+pub fn spawn_enemies(
+    mut commands: Commands,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    asset_server: Res<AssetServer>,
+    mut formation_maker: ResMut<FormationMaker>,
+    mut game_rng: ResMut<GameRng>,
+    mut rip: ResMut<RollbackIdProvider>,
+) {
+    let window = window_query.get_single().unwrap();
+
+    for _ in 0..ENEMY_PER_ROW {
+        let formation = formation_maker.make(window, &mut game_rng.0);
+
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(formation.start.x, formation.start.y, 0.0),
+                texture: asset_server.load("png/Enemies/enemyRed3.png"),
+                ..Default::default()
+            },
+            Enemy {},
+            Health::new(ENEMY_MAX_HEALTH),
+            formation,
+            EnemyFireTimer::default(),
+            RigidBody::KinematicPositionBased,
+            Collider::ball(ENEMY_SIZE / 2.0),
+            ActiveEvents::COLLISION_EVENTS,
+            Rollback::new(rip.next_id()),
+        ));
+    }
+}
+
+// Runs inside the GGRS rollback schedule, alongside enemy_movement and
+// enemy_bullet_movement, so the fire timer advances by the fixed timestep
+// instead of `Res<Time>` - enemy return fire is a real damage source and
+// must stay reproducible across resimulation like the rest of gameplay.
+pub fn enemy_fire(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    mut rip: ResMut<RollbackIdProvider>,
+    mut enemy_query: Query<(&Transform, &mut EnemyFireTimer), With<Enemy>>,
+) {
+    for (enemy_transform, mut fire_timer) in enemy_query.iter_mut() {
+        fire_timer.timer.tick(Duration::from_secs_f32(FIXED_DELTA));
+
+        if !fire_timer.timer.just_finished() {
+            continue;
+        }
+
+        let translation = enemy_transform.translation;
+
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(translation.x, translation.y, 0.0),
+                texture: asset_server.load("png/Lasers/laserRed05.png"),
+                ..Default::default()
+            },
+            EnemyBullet {},
+            Damage {
+                amount: ENEMY_BULLET_DAMAGE,
+            },
+            RigidBody::KinematicPositionBased,
+            Collider::ball(ENEMY_BULLET_SIZE / 2.0),
+            ActiveEvents::COLLISION_EVENTS,
+            Rollback::new(rip.next_id()),
+        ));
+
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("audio/sfx_laser1.ogg"),
+                settings: PlaybackSettings {
+                    volume: settings.sfx_gain(1.0),
+                    ..PlaybackSettings::ONCE
+                },
+            },
+            EnemyFireSound,
+        ));
+    }
+}
+
+pub fn enemy_bullet_movement(mut bullet_query: Query<&mut Transform, With<EnemyBullet>>) {
+    for mut bullet_transform in bullet_query.iter_mut() {
+        let direction = Vec3::new(0.0, -1.0, 0.0);
+        bullet_transform.translation += direction * ENEMY_BULLET_SPEED * FIXED_DELTA;
+    }
+}
+
+pub fn enemy_bullet_bounds(
+    mut commands: Commands,
+    bullet_query: Query<(Entity, &Transform), With<EnemyBullet>>,
+) {
+    let half_bullet_size = ENEMY_BULLET_SIZE / 2.0;
+
+    for (bullet_entity, bullet_transform) in bullet_query.iter() {
+        if bullet_transform.translation.y < -half_bullet_size {
+            commands.entity(bullet_entity).despawn();
+        }
+    }
+}
+
+// Runs inside the GGRS rollback schedule, so it advances by the fixed
+// timestep rather than `Res<Time>` - rollback frames must be reproducible
+// regardless of real wall-clock jitter.
+pub fn enemy_movement(mut enemy_query: Query<(&mut Transform, &mut Formation)>) {
+    for (mut enemy_transform, mut formation) in enemy_query.iter_mut() {
+        let translation = enemy_transform.translation;
+
+        // Fly in toward the formation's pivot until close enough to join the orbit.
+        if translation.truncate().distance(formation.pivot) > formation.radius.length() * 0.95 {
+            let target = Vec3::new(formation.pivot.x, formation.pivot.y, 0.0);
+            let direction = (target - translation).normalize_or_zero();
+            enemy_transform.translation += direction * ENEMY_SPEED * FIXED_DELTA;
+            continue;
+        }
+
+        formation.angle += formation.speed * FIXED_DELTA;
+
+        let target = formation.pivot
+            + Vec2::new(
+                formation.angle.cos() * formation.radius.x,
+                formation.angle.sin() * formation.radius.y,
+            );
+        let direction = (Vec3::new(target.x, target.y, 0.0) - translation).normalize_or_zero();
+        enemy_transform.translation += direction * ENEMY_SPEED * FIXED_DELTA;
+    }
+}
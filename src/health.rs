@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+const HIT_FLASH_SEC: f32 = 0.15;
+
+const LIVES_START: u32 = 3;
+
+#[derive(Component, Clone)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Health { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+}
+
+#[derive(Component)]
+pub struct Damage {
+    pub amount: f32,
+}
+
+#[derive(Resource, Clone)]
+pub struct Lives {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Default for Lives {
+    fn default() -> Lives {
+        Lives {
+            current: LIVES_START,
+            max: LIVES_START,
+        }
+    }
+}
+
+/// A brief sprite tint applied on a non-lethal hit, reverted by [`fade_hit_flash`].
+#[derive(Component)]
+pub struct HitFlash {
+    pub timer: Timer,
+}
+
+impl Default for HitFlash {
+    fn default() -> HitFlash {
+        HitFlash {
+            timer: Timer::from_seconds(HIT_FLASH_SEC, TimerMode::Once),
+        }
+    }
+}
+
+/// Scales SFX volume by how much of a target's max health a hit chipped away,
+/// so heavy hits land louder than grazes.
+pub fn hit_feedback_gain(damage: f32, max_health: f32) -> f32 {
+    (damage / max_health).clamp(0.3, 1.0)
+}
+
+pub fn flash_on_hit(commands: &mut Commands, entity: Entity, sprite: &mut Sprite) {
+    sprite.color = Color::rgb(1.0, 0.3, 0.3);
+    commands.entity(entity).insert(HitFlash::default());
+}
+
+pub fn fade_hit_flash(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut HitFlash)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut flash) in query.iter_mut() {
+        flash.timer.tick(time.delta());
+
+        if flash.timer.finished() {
+            sprite.color = Color::WHITE;
+            commands.entity(entity).remove::<HitFlash>();
+        }
+    }
+}
@@ -1,20 +1,40 @@
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
+use bevy_ggrs::{GgrsApp, GgrsPlugin, GgrsSchedule, PlayerInputs, ReadInputs, Rollback, RollbackIdProvider};
+use bevy_rapier2d::prelude::*;
+use structopt::StructOpt;
+
+mod formation;
+mod health;
+mod netplay;
+mod physics;
+mod settings;
+mod ui;
+
+use formation::FormationMaker;
+use health::{Damage, Health, Lives};
+use netplay::{GGRSConfig, GameRng, Opt, PlayerInput, PreviousInput, FIXED_DELTA};
+use settings::{MusicTable, Settings};
 
 const GAMEAREA_PADDING: f32 = 80.0;
 
 const PLAYER_SPEED: f32 = 2000.0;
 const PLAYER_SIZE: f32 = 90.0;
+const PLAYER_MAX_HEALTH: f32 = 100.0;
 
 const ENEMY_PER_ROW: usize = 4;
 const ENEMY_SPEED: f32 = 1000.0;
 const ENEMY_SIZE: f32 = 102.0;
-const ENEMY_SPACING: f32 = 120.0;
+const ENEMY_MAX_HEALTH: f32 = 30.0;
 
 const BULLET_SPEED: f32 = 1000.0;
 const BULLET_SIZE: f32 = 9.0;
+const BULLET_DAMAGE: f32 = 15.0;
 
-const ENEMY_SPAWN_TIME_SEC: f32 = 2.0;
+const ENEMY_BULLET_SPEED: f32 = 700.0;
+const ENEMY_BULLET_SIZE: f32 = 9.0;
+const ENEMY_BULLET_DAMAGE: f32 = 10.0;
+const ENEMY_FIRE_TIME_SEC: f32 = 3.0;
 
 #[derive(SystemSet, States, PartialEq, Eq, Debug, Clone, Hash, Default)]
 enum AppState {
@@ -32,17 +52,18 @@ enum SimulationState {
 }
 
 #[derive(Component)]
-struct Player {}
+struct Player {
+    pub handle: usize,
+}
 
 #[derive(Component)]
 struct Bullet {}
 
+#[derive(Component)]
+struct EnemyBullet {}
+
 #[derive(Component, Debug)]
-struct Enemy {
-    pub direction: Vec2,
-    pub row: usize,
-    pub col: usize,
-}
+struct Enemy {}
 
 #[derive(Component)]
 struct BounceSound;
@@ -56,7 +77,10 @@ struct BulletSpawnSound;
 #[derive(Component)]
 struct BulletHitSound;
 
-#[derive(Default, Resource)]
+#[derive(Component)]
+struct EnemyFireSound;
+
+#[derive(Default, Resource, Clone)]
 struct Score {
     pub value: u32,
 }
@@ -66,49 +90,119 @@ struct GameOver {
     pub score: u32,
 }
 
-#[derive(Resource)]
-struct EnemySpawnTimer {
-    pub timer: Timer,
-}
-
-impl Default for EnemySpawnTimer {
-    fn default() -> EnemySpawnTimer {
-        EnemySpawnTimer {
-            timer: Timer::from_seconds(ENEMY_SPAWN_TIME_SEC, TimerMode::Repeating),
-        }
-    }
-}
-
 fn main() {
+    let opt = Opt::from_args();
+    let session = netplay::build_ggrs_session(&opt);
+
     App::new()
         .add_plugins(DefaultPlugins)
+        .add_plugins(GgrsPlugin::<GGRSConfig>::default())
+        // Rapier's systems default to PostUpdate, which only runs once per
+        // App::update - but GGRS can resimulate several GgrsSchedule frames
+        // within a single App::update during a rollback. Run physics inside
+        // GgrsSchedule too so collision detection (and the CollisionEvents
+        // our hit-consumer systems key off) stays in lockstep with every
+        // resimulated frame instead of only the most recent one.
+        .add_plugins(
+            RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0).in_schedule(GgrsSchedule),
+        )
         .add_state::<AppState>()
         .add_state::<SimulationState>()
         .add_event::<GameOver>()
         .init_resource::<Score>()
-        .init_resource::<EnemySpawnTimer>()
-        .add_systems(Startup, spawn_camera)
+        .init_resource::<FormationMaker>()
+        .init_resource::<Lives>()
+        .init_resource::<physics::PlayerHitThisFrame>()
+        .insert_resource(GameRng::from_seed(opt.seed))
+        .insert_resource(opt)
+        .insert_resource(session)
+        .insert_resource(Settings::load())
+        .init_resource::<MusicTable>()
+        .set_rollback_schedule_fps(netplay::FPS)
+        .rollback_component_with_clone::<Transform>()
+        .rollback_component_with_clone::<PreviousInput>()
+        .rollback_component_with_clone::<formation::Formation>()
+        .rollback_component_with_clone::<Health>()
+        .rollback_component_with_clone::<formation::EnemyFireTimer>()
+        .rollback_resource_with_clone::<FormationMaker>()
+        .rollback_resource_with_clone::<GameRng>()
+        .rollback_resource_with_clone::<Lives>()
+        .rollback_resource_with_clone::<Score>()
+        .add_systems(Startup, (spawn_camera, physics::spawn_walls))
         .add_systems(Update, bevy::window::close_on_esc)
-        .add_systems(OnEnter(AppState::Game), (spawn_player, spawn_enemies))
-        .add_systems(OnExit(AppState::Game), despawn_entities)
-        .add_systems(Update, (toggle_appstate))
+        .add_systems(
+            OnEnter(AppState::MainMenu),
+            (settings::play_menu_music, ui::spawn_main_menu),
+        )
+        .add_systems(OnExit(AppState::MainMenu), ui::despawn_main_menu)
+        .add_systems(
+            OnEnter(AppState::Game),
+            (
+                netplay::spawn_players,
+                formation::spawn_enemies,
+                settings::play_game_music,
+                ui::spawn_scoreboard,
+            ),
+        )
+        .add_systems(OnEnter(AppState::GameOver), settings::play_game_over_music)
+        .add_systems(
+            OnExit(AppState::Game),
+            (despawn_entities, ui::despawn_scoreboard, ui::despawn_paused_overlay),
+        )
+        .add_systems(
+            OnEnter(SimulationState::Paused),
+            (settings::pause_game_music, ui::spawn_paused_overlay).run_if(in_state(AppState::Game)),
+        )
+        .add_systems(
+            OnEnter(SimulationState::Running),
+            (settings::resume_game_music, ui::despawn_paused_overlay).run_if(in_state(AppState::Game)),
+        )
+        .add_systems(Update, (toggle_appstate, settings::fade_out_music))
         .add_systems(Update, (toggle_simulation).run_if(in_state(AppState::Game)))
+        .add_systems(Last, settings::save_settings_on_exit)
+        .add_systems(ReadInputs, netplay::read_local_inputs)
         .add_systems(
-            Update,
+            GgrsSchedule,
             (
                 player_movement,
-                player_bounds.after(player_movement),
-                enemy_movement,
-                enemy_bounds.after(enemy_movement),
-                enemy_direction.after(enemy_bounds),
-                enemy_hit_player,
-                // enemy_spawn_cycle,
+                physics::player_bounds.after(player_movement),
+                formation::enemy_movement,
+                physics::enemy_bounds.after(formation::enemy_movement),
                 bullet_spawn,
                 bullet_movement,
+                formation::enemy_bullet_movement,
+                formation::enemy_fire,
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        // Consumes the CollisionEvents Rapier's own GgrsSchedule-bound systems
+        // (above) produced for this rollback frame, so hits - and the
+        // Score/Health/Lives mutations they drive - stay reproducible across
+        // peers and resimulation just like the movement systems do.
+        .add_systems(
+            GgrsSchedule,
+            (
+                physics::bullet_hit_enemy.after(PhysicsSet::Writeback),
+                physics::reset_player_hits.after(PhysicsSet::Writeback),
+                physics::enemy_hit_player
+                    .after(PhysicsSet::Writeback)
+                    .after(physics::reset_player_hits),
+                physics::enemy_bullet_hit_player
+                    .after(PhysicsSet::Writeback)
+                    .after(physics::reset_player_hits),
+            )
+                .run_if(in_state(AppState::Game))
+                .run_if(in_state(SimulationState::Running)),
+        )
+        .add_systems(
+            Update,
+            (
                 bullet_bounds,
-                bullet_hit_enemy,
-                update_score,
+                formation::enemy_bullet_bounds,
+                ui::update_scoreboard,
                 handle_game_over,
+                health::fade_hit_flash,
             )
                 .run_if(in_state(AppState::Game))
                 .run_if(in_state(SimulationState::Running)),
@@ -132,6 +226,10 @@ fn toggle_appstate(
             dbg!("SimulationState::MainMenu");
             commands.insert_resource(NextState(Some(AppState::MainMenu)));
         }
+        if app_state.get() == &AppState::GameOver {
+            dbg!("SimulationState::MainMenu");
+            commands.insert_resource(NextState(Some(AppState::MainMenu)));
+        }
     }
 }
 
@@ -144,49 +242,6 @@ fn spawn_camera(mut commands: Commands, window_query: Query<&Window, With<Primar
     });
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-) {
-    let window = window_query.get_single().unwrap();
-
-    commands.spawn((
-        SpriteBundle {
-            transform: Transform::from_xyz(window.width() / 2.0, window.height() / 2.0, 0.0),
-            texture: asset_server.load("png/ufoGreen.png"),
-            ..Default::default()
-        },
-        Player {},
-    ));
-}
-
-fn spawn_enemies(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    asset_server: Res<AssetServer>,
-) {
-    let window = window_query.get_single().unwrap();
-
-    for i in 0..ENEMY_PER_ROW {
-        let x = GAMEAREA_PADDING + ENEMY_SPACING * i as f32;
-        let y = window.height() - GAMEAREA_PADDING;
-
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(x, y, 0.0),
-                texture: asset_server.load("png/Enemies/enemyRed3.png"),
-                ..Default::default()
-            },
-            Enemy {
-                direction: Vec2::new(1.0, 0.0).normalize(),
-                row: 0,
-                col: i,
-            },
-        ));
-    }
-}
-
 fn despawn_entities(
     mut commands: Commands,
     player_query: Query<Entity, With<Player>>,
@@ -194,7 +249,7 @@ fn despawn_entities(
 ) {
     dbg!("despawn_entities");
 
-    if let Ok(player_entity) = player_query.get_single() {
+    for player_entity in player_query.iter() {
         commands.entity(player_entity).despawn();
     }
 
@@ -204,23 +259,23 @@ fn despawn_entities(
 }
 
 fn player_movement(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut player_query: Query<&mut Transform, With<Player>>,
-    time: Res<Time>,
+    inputs: Res<PlayerInputs<GGRSConfig>>,
+    mut player_query: Query<(&Player, &mut Transform)>,
 ) {
-    if let Ok(mut player_transform) = player_query.get_single_mut() {
+    for (player, mut player_transform) in player_query.iter_mut() {
+        let (input, _) = inputs[player.handle];
         let mut direction = Vec3::ZERO;
 
-        if keyboard_input.pressed(KeyCode::Left) {
+        if input.contains(PlayerInput::LEFT) {
             direction += Vec3::new(-1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Right) {
+        if input.contains(PlayerInput::RIGHT) {
             direction += Vec3::new(1.0, 0.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Up) {
+        if input.contains(PlayerInput::UP) {
             direction += Vec3::new(0.0, 1.0, 0.0);
         }
-        if keyboard_input.pressed(KeyCode::Down) {
+        if input.contains(PlayerInput::DOWN) {
             direction += Vec3::new(0.0, -1.0, 0.0);
         }
 
@@ -228,186 +283,24 @@ fn player_movement(
             direction = direction.normalize();
         }
 
-        player_transform.translation += direction * PLAYER_SPEED * time.delta_seconds();
-    }
-}
-
-fn player_bounds(
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut player_query: Query<&mut Transform, With<Player>>,
-) {
-    let window = window_query.get_single().unwrap();
-    let half_player_size = PLAYER_SIZE / 2.0;
-
-    let x_min = half_player_size;
-    let x_max = window.width() - half_player_size;
-    let y_min = half_player_size;
-    let y_max = window.height() - half_player_size;
-
-    if let Ok(mut player_transform) = player_query.get_single_mut() {
-        let mut translation = player_transform.translation;
-
-        if translation.x < x_min {
-            translation.x = x_min;
-        } else if translation.x > x_max {
-            translation.x = x_max;
-        }
-
-        if translation.y < y_min {
-            translation.y = y_min;
-        } else if translation.y > y_max {
-            translation.y = y_max;
-        }
-
-        player_transform.translation = translation;
-    }
-}
-
-fn enemy_movement(mut enemy_query: Query<(&mut Transform, &Enemy)>, time: Res<Time>) {
-    dbg!("runninig");
-    for (mut enemy_transform, enemy) in enemy_query.iter_mut() {
-        let direction = Vec3::new(enemy.direction.x, enemy.direction.y, 0.0);
-        enemy_transform.translation += direction * ENEMY_SPEED * time.delta_seconds();
-    }
-}
-
-fn enemy_bounds(
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut enemy_query: Query<(&mut Transform, &Enemy)>,
-) {
-    let window = window_query.get_single().unwrap();
-    let half_enemy_size = ENEMY_SIZE / 2.0;
-
-    for (mut enemy_transform, enemy) in enemy_query.iter_mut() {
-        let x_min = GAMEAREA_PADDING + (ENEMY_SPACING * enemy.col as f32) + half_enemy_size;
-        let x_max = window.width()
-            - GAMEAREA_PADDING
-            - (ENEMY_SPACING * (ENEMY_PER_ROW - enemy.col - 1) as f32)
-            - half_enemy_size;
-
-        let mut translation = enemy_transform.translation;
-
-        if translation.x < x_min {
-            translation.x = x_min;
-        } else if translation.x > x_max {
-            translation.x = x_max;
-        }
-
-        enemy_transform.translation = translation;
-    }
-}
-
-fn enemy_direction(
-    mut _commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut enemy_query: Query<(&Transform, &mut Enemy)>,
-    _asset_server: Res<AssetServer>,
-) {
-    let window = window_query.get_single().unwrap();
-    let half_enemy_size = ENEMY_SIZE / 2.0;
-
-    for (enemy_transform, mut enemy) in enemy_query.iter_mut() {
-        let x_min = GAMEAREA_PADDING + (ENEMY_SPACING * enemy.col as f32) + half_enemy_size;
-        let x_max = window.width()
-            - GAMEAREA_PADDING
-            - (ENEMY_SPACING * (ENEMY_PER_ROW - enemy.col - 1) as f32)
-            - half_enemy_size;
-
-        let translation = enemy_transform.translation;
-        let mut direction_changed = false;
-
-        if translation.x == x_min || translation.x == x_max {
-            enemy.direction.x *= -1.0;
-            direction_changed = true;
-        }
-
-        if direction_changed {
-            // commands.spawn((
-            //     AudioBundle {
-            //         source: asset_server.load("audio/sfx_zap.ogg"),
-            //         settings: PlaybackSettings::ONCE,
-            //     },
-            //     BounceSound,
-            // ));
-        }
-    }
-}
-
-fn enemy_hit_player(
-    mut commands: Commands,
-    mut game_over_event_writer: EventWriter<GameOver>,
-    mut player_query: Query<(Entity, &Transform), With<Player>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
-    asset_server: Res<AssetServer>,
-    score: Res<Score>,
-) {
-    if let Ok((player_entity, player_transform)) = player_query.get_single_mut() {
-        for (enemy_entity, enemy_transform) in enemy_query.iter() {
-            let distance = player_transform
-                .translation
-                .distance(enemy_transform.translation);
-            let player_radius = PLAYER_SIZE / 2.0;
-            let enemy_radius = ENEMY_SIZE / 2.0;
-
-            if distance < player_radius + enemy_radius {
-                commands.spawn((
-                    AudioBundle {
-                        source: asset_server.load("audio/sfx_lose.ogg"),
-                        settings: PlaybackSettings::ONCE,
-                    },
-                    LoseSound,
-                ));
-
-                commands.entity(enemy_entity).despawn();
-                commands.entity(player_entity).despawn();
-
-                game_over_event_writer.send(GameOver { score: score.value });
-
-                break;
-            }
-        }
-    }
-    // commands.spawn((
-    //     AudioBundle {
-    //         source: asset_server.load("audio/sfx_lose.ogg"),
-    //         settings: PlaybackSettings::ONCE,
-    //     },
-    //     BounceSound,
-    // ));
-}
-
-fn enemy_spawn_cycle(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut enemy_spawn_timer: ResMut<EnemySpawnTimer>,
-    time: Res<Time>,
-) {
-    enemy_spawn_timer.timer.tick(time.delta());
-
-    if enemy_spawn_timer.timer.finished() {
-        commands.spawn((
-            SpriteBundle {
-                transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                texture: asset_server.load("png/Enemies/enemyRed3.png"),
-                ..Default::default()
-            },
-            Enemy {
-                direction: Vec2::new(1.0, 1.0).normalize(),
-                row: 0,
-                col: 0,
-            },
-        ));
+        player_transform.translation += direction * PLAYER_SPEED * FIXED_DELTA;
     }
 }
 
 fn bullet_spawn(
-    keyboard_input: Res<Input<KeyCode>>,
+    inputs: Res<PlayerInputs<GGRSConfig>>,
     mut commands: Commands,
-    player_query: Query<&Transform, With<Player>>,
+    mut rip: ResMut<RollbackIdProvider>,
+    mut player_query: Query<(&Player, &Transform, &mut PreviousInput)>,
     asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        if let Ok(player_transform) = player_query.get_single() {
+    for (player, player_transform, mut previous_input) in player_query.iter_mut() {
+        let (input, _) = inputs[player.handle];
+        let just_fired = input.contains(PlayerInput::FIRE) && !previous_input.0.contains(PlayerInput::FIRE);
+        previous_input.0 = input;
+
+        if just_fired {
             let translation = player_transform.translation;
 
             commands.spawn((
@@ -417,12 +310,20 @@ fn bullet_spawn(
                     ..Default::default()
                 },
                 Bullet {},
+                Damage { amount: BULLET_DAMAGE },
+                RigidBody::KinematicPositionBased,
+                Collider::ball(BULLET_SIZE / 2.0),
+                ActiveEvents::COLLISION_EVENTS,
+                Rollback::new(rip.next_id()),
             ));
 
             commands.spawn((
                 AudioBundle {
                     source: asset_server.load("audio/sfx_laser1.ogg"),
-                    settings: PlaybackSettings::ONCE,
+                    settings: PlaybackSettings {
+                        volume: settings.sfx_gain(1.0),
+                        ..PlaybackSettings::ONCE
+                    },
                 },
                 BulletSpawnSound,
             ));
@@ -430,10 +331,10 @@ fn bullet_spawn(
     }
 }
 
-fn bullet_movement(mut bullet_query: Query<&mut Transform, With<Bullet>>, time: Res<Time>) {
+fn bullet_movement(mut bullet_query: Query<&mut Transform, With<Bullet>>) {
     for mut bullet_transform in bullet_query.iter_mut() {
         let direction = Vec3::new(0.0, 1.0, 0.0);
-        bullet_transform.translation += direction * BULLET_SPEED * time.delta_seconds();
+        bullet_transform.translation += direction * BULLET_SPEED * FIXED_DELTA;
     }
 }
 
@@ -455,59 +356,18 @@ fn bullet_bounds(
     }
 }
 
-fn bullet_hit_enemy(
+fn handle_game_over(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Transform), With<Bullet>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
-    asset_server: Res<AssetServer>,
-    mut score: ResMut<Score>,
+    mut game_over_event_reader: EventReader<GameOver>,
+    mut lives: ResMut<Lives>,
+    mut settings: ResMut<Settings>,
 ) {
-    for (bullet_entity, bullet_transform) in bullet_query.iter() {
-        for (enemy_entity, enemy_transform) in enemy_query.iter() {
-            let distance = bullet_transform
-                .translation
-                .distance(enemy_transform.translation);
-            let bullet_radius = BULLET_SIZE / 2.0;
-            let enemy_radius = ENEMY_SIZE / 2.0;
-
-            if distance < bullet_radius + enemy_radius {
-                println!("BULLET HIT");
-
-                score.value += 1;
-
-                commands.spawn((
-                    AudioBundle {
-                        source: asset_server.load("audio/sfx_laser2.ogg"),
-                        settings: PlaybackSettings::ONCE,
-                    },
-                    BulletHitSound,
-                ));
-
-                commands.entity(bullet_entity).despawn();
-                commands.entity(enemy_entity).despawn();
-            }
-        }
-    }
-    // commands.spawn((
-    //     AudioBundle {
-    //         source: asset_server.load("audio/sfx_lose.ogg"),
-    //         settings: PlaybackSettings::ONCE,
-    //     },
-    //     BounceSound,
-    // ));
-}
-
-fn update_score(score: Res<Score>) {
-    if score.is_changed() {
-        println!("{}", score.value);
-    }
-}
-
-fn handle_game_over(mut commands: Commands, mut game_over_event_reader: EventReader<GameOver>) {
     for event in game_over_event_reader.iter() {
         println!("GAME OVER. Final Score: {}", event.score);
-        dbg!("SimulationState::MainMenu");
-        commands.insert_resource(NextState(Some(AppState::MainMenu)));
+        lives.current = lives.max;
+        settings.high_score = settings.high_score.max(event.score);
+        dbg!("SimulationState::GameOver");
+        commands.insert_resource(NextState(Some(AppState::GameOver)));
     }
 }
 
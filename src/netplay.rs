@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ggrs::ggrs::{self, Config, PlayerType, SessionBuilder};
+use bevy_ggrs::{LocalInputs, LocalPlayers, Rollback, RollbackIdProvider};
+use bevy_rapier2d::prelude::*;
+use bytemuck::{Pod, Zeroable};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use structopt::StructOpt;
+
+use crate::health::Health;
+use crate::{Player, PLAYER_MAX_HEALTH, PLAYER_SIZE};
+
+pub const FPS: usize = 60;
+pub const FIXED_DELTA: f32 = 1.0 / FPS as f32;
+pub const INPUT_DELAY_DEFAULT: usize = 2;
+
+bitflags::bitflags! {
+    #[derive(Default)]
+    pub struct PlayerInput: u8 {
+        const LEFT  = 1 << 0;
+        const RIGHT = 1 << 1;
+        const UP    = 1 << 2;
+        const DOWN  = 1 << 3;
+        const FIRE  = 1 << 4;
+    }
+}
+
+unsafe impl Pod for PlayerInput {}
+unsafe impl Zeroable for PlayerInput {}
+
+/// The `PlayerInput` seen on the previous rollback frame for a given player,
+/// kept as a rollback-registered component so edge detection (e.g. firing
+/// once per press rather than once per tick) stays correct across
+/// resimulation.
+#[derive(Component, Clone, Copy, Default)]
+pub struct PreviousInput(pub PlayerInput);
+
+#[derive(Debug)]
+pub struct GGRSConfig;
+
+impl Config for GGRSConfig {
+    type Input = PlayerInput;
+    type State = u8;
+    type Address = SocketAddr;
+}
+
+/// A deterministic RNG shared by every rollback-relevant system (e.g. the
+/// formation generator) so both peers in a session produce identical frames.
+#[derive(Resource, Clone)]
+pub struct GameRng(pub StdRng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        GameRng(StdRng::seed_from_u64(seed))
+    }
+}
+
+#[derive(StructOpt, Resource, Clone, Debug)]
+#[structopt(name = "bevy-space-shooter")]
+pub struct Opt {
+    #[structopt(short, long)]
+    pub local_port: u16,
+
+    #[structopt(short, long)]
+    pub players: Vec<String>,
+
+    #[structopt(short, long, default_value = "2")]
+    pub input_delay: usize,
+
+    /// Seed for `GameRng`, agreed out-of-band between peers (e.g. picked by
+    /// whoever hosts) and passed identically on both command lines. Unlike
+    /// `--local-port`, which is by construction different per peer, this
+    /// value must match exactly or the two sides' formation generators will
+    /// diverge from frame one.
+    #[structopt(short, long, default_value = "0")]
+    pub seed: u64,
+}
+
+pub fn read_local_inputs(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    local_players: Res<LocalPlayers>,
+) {
+    let mut local_inputs = HashMap::new();
+
+    for handle in &local_players.0 {
+        let mut input = PlayerInput::empty();
+
+        if keyboard_input.pressed(KeyCode::Left) {
+            input |= PlayerInput::LEFT;
+        }
+        if keyboard_input.pressed(KeyCode::Right) {
+            input |= PlayerInput::RIGHT;
+        }
+        if keyboard_input.pressed(KeyCode::Up) {
+            input |= PlayerInput::UP;
+        }
+        if keyboard_input.pressed(KeyCode::Down) {
+            input |= PlayerInput::DOWN;
+        }
+        if keyboard_input.pressed(KeyCode::Space) {
+            input |= PlayerInput::FIRE;
+        }
+
+        local_inputs.insert(*handle, input);
+    }
+
+    commands.insert_resource(LocalInputs::<GGRSConfig>(local_inputs));
+}
+
+pub fn build_ggrs_session(opt: &Opt) -> ggrs::P2PSession<GGRSConfig> {
+    let mut session_builder = SessionBuilder::<GGRSConfig>::new()
+        .with_num_players(opt.players.len())
+        .with_input_delay(opt.input_delay);
+
+    for (handle, player_addr) in opt.players.iter().enumerate() {
+        if player_addr == "localhost" {
+            session_builder = session_builder
+                .add_player(PlayerType::Local, handle)
+                .expect("failed to register local player");
+        } else {
+            let remote_addr: SocketAddr = player_addr
+                .parse()
+                .expect("player address must be `localhost` or `ip:port`");
+
+            session_builder = session_builder
+                .add_player(PlayerType::Remote(remote_addr), handle)
+                .expect("failed to register remote player");
+        }
+    }
+
+    let socket = ggrs::UdpNonBlockingSocket::bind_to_port(opt.local_port)
+        .expect("failed to bind local UDP socket");
+
+    session_builder
+        .start_p2p_session(socket)
+        .expect("failed to start GGRS session")
+}
+
+pub fn spawn_players(
+    mut commands: Commands,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    asset_server: Res<AssetServer>,
+    mut rip: ResMut<RollbackIdProvider>,
+    opt: Res<Opt>,
+) {
+    let window = window_query.get_single().unwrap();
+    let y = window.height() / 2.0;
+
+    for handle in 0..opt.players.len() {
+        let x = window.width() / 2.0 + if handle == 0 { -100.0 } else { 100.0 };
+
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(x, y, 0.0),
+                texture: asset_server.load("png/ufoGreen.png"),
+                ..Default::default()
+            },
+            Player { handle },
+            Health::new(PLAYER_MAX_HEALTH),
+            PreviousInput::default(),
+            RigidBody::KinematicPositionBased,
+            Collider::ball(PLAYER_SIZE / 2.0),
+            ActiveEvents::COLLISION_EVENTS,
+            Rollback::new(rip.next_id()),
+        ));
+    }
+}
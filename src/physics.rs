@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_rapier2d::prelude::*;
+
+use crate::health::{flash_on_hit, hit_feedback_gain, Damage, Health, Lives};
+use crate::settings::Settings;
+use crate::{
+    Bullet, BulletHitSound, Enemy, EnemyBullet, GameOver, LoseSound, Player, Score,
+    ENEMY_SIZE, GAMEAREA_PADDING, PLAYER_SIZE,
+};
+
+const WALL_THICKNESS: f32 = 20.0;
+
+#[derive(Component)]
+pub struct AreaWall;
+
+/// Players already credited with a lost life this frame, shared by
+/// `enemy_hit_player` and `enemy_bullet_hit_player` so a single tick can't
+/// cost more than one life regardless of which source (or how many of each)
+/// hit the player. Cleared by [`reset_player_hits`] before either system runs.
+#[derive(Resource, Default)]
+pub struct PlayerHitThisFrame(HashSet<Entity>);
+
+pub fn reset_player_hits(mut hit_this_frame: ResMut<PlayerHitThisFrame>) {
+    hit_this_frame.0.clear();
+}
+
+pub fn spawn_walls(mut commands: Commands, window_query: Query<&Window, With<PrimaryWindow>>) {
+    let window = window_query.get_single().unwrap();
+
+    let x_min = GAMEAREA_PADDING;
+    let x_max = window.width() - GAMEAREA_PADDING;
+    let y_min = GAMEAREA_PADDING;
+    let y_max = window.height() - GAMEAREA_PADDING;
+
+    let walls = [
+        (Vec2::new(x_min, window.height() / 2.0), Vec2::new(WALL_THICKNESS, window.height())),
+        (Vec2::new(x_max, window.height() / 2.0), Vec2::new(WALL_THICKNESS, window.height())),
+        (Vec2::new(window.width() / 2.0, y_min), Vec2::new(window.width(), WALL_THICKNESS)),
+        (Vec2::new(window.width() / 2.0, y_max), Vec2::new(window.width(), WALL_THICKNESS)),
+    ];
+
+    for (position, size) in walls {
+        commands.spawn((
+            TransformBundle::from(Transform::from_xyz(position.x, position.y, 0.0)),
+            RigidBody::Fixed,
+            Collider::cuboid(size.x / 2.0, size.y / 2.0),
+            AreaWall,
+        ));
+    }
+}
+
+// `Player`/`Enemy`/`Bullet` are `RigidBody::KinematicPositionBased` with their
+// `Transform` written directly by the movement systems, so Rapier never
+// resolves them against the `AreaWall` colliders (that requires driving
+// movement through a `KinematicCharacterController`, which we don't use
+// here). The walls stay as collidable geometry for `CollisionEvent`s, but we
+// still need this clamp so the player can't fly off the play area.
+pub fn player_bounds(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut player_query: Query<&mut Transform, With<Player>>,
+) {
+    let window = window_query.get_single().unwrap();
+    let half_player_size = PLAYER_SIZE / 2.0;
+
+    let x_min = GAMEAREA_PADDING + half_player_size;
+    let x_max = window.width() - GAMEAREA_PADDING - half_player_size;
+    let y_min = GAMEAREA_PADDING + half_player_size;
+    let y_max = window.height() - GAMEAREA_PADDING - half_player_size;
+
+    for mut player_transform in player_query.iter_mut() {
+        let mut translation = player_transform.translation;
+
+        translation.x = translation.x.clamp(x_min, x_max);
+        translation.y = translation.y.clamp(y_min, y_max);
+
+        player_transform.translation = translation;
+    }
+}
+
+// Same rationale as `player_bounds`: Enemy is also KinematicPositionBased, so
+// it never collides against `AreaWall`, and a wide enough orbit radius can
+// carry it past the side/bottom walls. The top is left unclamped since
+// enemies spawn above the window and fly down into formation - clamping it
+// there would freeze that entry swoop at the spawn point. Bullet/EnemyBullet
+// don't need an equivalent clamp: they only ever move along the single axis
+// they were fired on (never sideways), and already despawn via
+// `bullet_bounds`/`enemy_bullet_bounds` the moment they cross that axis's
+// edge, which is their version of "can't leave the play area".
+pub fn enemy_bounds(
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut enemy_query: Query<&mut Transform, With<Enemy>>,
+) {
+    let window = window_query.get_single().unwrap();
+    let half_enemy_size = ENEMY_SIZE / 2.0;
+
+    let x_min = GAMEAREA_PADDING + half_enemy_size;
+    let x_max = window.width() - GAMEAREA_PADDING - half_enemy_size;
+    let y_min = GAMEAREA_PADDING + half_enemy_size;
+
+    for mut enemy_transform in enemy_query.iter_mut() {
+        let mut translation = enemy_transform.translation;
+
+        translation.x = translation.x.clamp(x_min, x_max);
+        translation.y = translation.y.max(y_min);
+
+        enemy_transform.translation = translation;
+    }
+}
+
+pub fn bullet_hit_enemy(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    bullet_query: Query<(Entity, &Damage), With<Bullet>>,
+    mut enemy_query: Query<(&mut Health, &mut Sprite), With<Enemy>>,
+    asset_server: Res<AssetServer>,
+    mut score: ResMut<Score>,
+    settings: Res<Settings>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+        let pair = [*a, *b];
+
+        let bullet = pair.into_iter().find_map(|entity| bullet_query.get(entity).ok());
+        let enemy_entity = pair.into_iter().find(|entity| enemy_query.contains(*entity));
+
+        let (Some((bullet_entity, damage)), Some(enemy_entity)) = (bullet, enemy_entity) else {
+            continue;
+        };
+
+        let Ok((mut health, mut sprite)) = enemy_query.get_mut(enemy_entity) else {
+            continue;
+        };
+
+        health.current -= damage.amount;
+
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("audio/sfx_laser2.ogg"),
+                settings: PlaybackSettings {
+                    volume: settings.sfx_gain(hit_feedback_gain(damage.amount, health.max)),
+                    ..PlaybackSettings::ONCE
+                },
+            },
+            BulletHitSound,
+        ));
+
+        commands.entity(bullet_entity).despawn();
+
+        if health.is_dead() {
+            score.value += 1;
+            commands.entity(enemy_entity).despawn();
+        } else {
+            flash_on_hit(&mut commands, enemy_entity, &mut sprite);
+        }
+    }
+}
+
+pub fn enemy_hit_player(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut player_query: Query<&mut Health, With<Player>>,
+    mut player_transform_query: Query<&mut Transform, With<Player>>,
+    enemy_query: Query<Entity, With<Enemy>>,
+    asset_server: Res<AssetServer>,
+    mut lives: ResMut<Lives>,
+    score: Res<Score>,
+    settings: Res<Settings>,
+    mut hit_this_frame: ResMut<PlayerHitThisFrame>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+        let pair = [*a, *b];
+
+        let player_entity = pair.into_iter().find(|entity| player_query.contains(*entity));
+        let enemy_entity = pair.into_iter().find(|entity| enemy_query.contains(*entity));
+
+        let (Some(player_entity), Some(enemy_entity)) = (player_entity, enemy_entity) else {
+            continue;
+        };
+
+        // Every enemy touching the player this frame dies on contact; only
+        // the first one also costs a life, so two simultaneous hits (or one
+        // from here and one from enemy_bullet_hit_player) don't drain two.
+        commands.entity(enemy_entity).despawn();
+
+        if !hit_this_frame.0.insert(player_entity) {
+            continue;
+        }
+
+        let Ok(mut health) = player_query.get_mut(player_entity) else {
+            continue;
+        };
+
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("audio/sfx_lose.ogg"),
+                settings: PlaybackSettings {
+                    volume: settings.sfx_gain(hit_feedback_gain(health.current, health.max)),
+                    ..PlaybackSettings::ONCE
+                },
+            },
+            LoseSound,
+        ));
+
+        health.current = 0.0;
+
+        if lives.current == 0 {
+            continue;
+        }
+
+        lives.current -= 1;
+
+        if lives.current == 0 {
+            commands.entity(player_entity).despawn();
+            game_over_event_writer.send(GameOver { score: score.value });
+        } else {
+            health.current = health.max;
+
+            let window = window_query.get_single().unwrap();
+            if let Ok(mut transform) = player_transform_query.get_mut(player_entity) {
+                transform.translation =
+                    Vec3::new(window.width() / 2.0, window.height() / 2.0, 0.0);
+            }
+        }
+    }
+}
+
+pub fn enemy_bullet_hit_player(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    mut game_over_event_writer: EventWriter<GameOver>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    bullet_query: Query<(Entity, &Damage), With<EnemyBullet>>,
+    mut player_query: Query<&mut Health, With<Player>>,
+    mut player_transform_query: Query<&mut Transform, With<Player>>,
+    asset_server: Res<AssetServer>,
+    mut lives: ResMut<Lives>,
+    score: Res<Score>,
+    settings: Res<Settings>,
+    mut hit_this_frame: ResMut<PlayerHitThisFrame>,
+) {
+    for event in collision_events.iter() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+        let pair = [*a, *b];
+
+        let bullet = pair.into_iter().find_map(|entity| bullet_query.get(entity).ok());
+        let player_entity = pair.into_iter().find(|entity| player_query.contains(*entity));
+
+        let (Some((bullet_entity, damage)), Some(player_entity)) = (bullet, player_entity) else {
+            continue;
+        };
+
+        let Ok(mut health) = player_query.get_mut(player_entity) else {
+            continue;
+        };
+
+        // Every bullet hitting the player this frame still deals its damage
+        // and despawns; only the first hit that actually kills the player
+        // costs a life, shared with enemy_hit_player via `hit_this_frame`.
+        health.current -= damage.amount;
+        commands.entity(bullet_entity).despawn();
+
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load("audio/sfx_lose.ogg"),
+                settings: PlaybackSettings {
+                    volume: settings.sfx_gain(hit_feedback_gain(damage.amount, health.max)),
+                    ..PlaybackSettings::ONCE
+                },
+            },
+            LoseSound,
+        ));
+
+        if !health.is_dead() {
+            continue;
+        }
+
+        if !hit_this_frame.0.insert(player_entity) {
+            continue;
+        }
+
+        if lives.current == 0 {
+            continue;
+        }
+
+        lives.current -= 1;
+
+        if lives.current == 0 {
+            commands.entity(player_entity).despawn();
+            game_over_event_writer.send(GameOver { score: score.value });
+        } else {
+            health.current = health.max;
+
+            let window = window_query.get_single().unwrap();
+            if let Ok(mut transform) = player_transform_query.get_mut(player_entity) {
+                transform.translation =
+                    Vec3::new(window.width() / 2.0, window.height() / 2.0, 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fs;
+
+use bevy::app::AppExit;
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_PATH: &str = "settings.ron";
+const MUSIC_FADE_SEC: f32 = 1.0;
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub high_score: u32,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.6,
+            high_score: 0,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Settings {
+        fs::read_to_string(SETTINGS_PATH)
+            .ok()
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Ok(contents) = ron::to_string(self) {
+            let _ = fs::write(SETTINGS_PATH, contents);
+        }
+    }
+
+    /// Final gain for a one-shot sound effect, after the saved SFX/master sliders.
+    pub fn sfx_gain(&self, base_gain: f32) -> f32 {
+        base_gain * self.sfx_volume * self.master_volume
+    }
+
+    fn music_gain(&self) -> f32 {
+        self.music_volume * self.master_volume
+    }
+}
+
+pub fn save_settings_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    settings: Res<Settings>,
+    score: Res<crate::Score>,
+) {
+    for _ in exit_events.iter() {
+        let mut settings = settings.clone();
+        settings.high_score = settings.high_score.max(score.value);
+        settings.save();
+    }
+}
+
+/// Maps an `AppState` to the looping track that should play while it's active.
+#[derive(Resource)]
+pub struct MusicTable {
+    pub tracks: Vec<String>,
+    pub state_tracks: HashMap<String, String>,
+}
+
+impl Default for MusicTable {
+    fn default() -> MusicTable {
+        let tracks = vec![
+            "audio/music_menu.ogg".to_string(),
+            "audio/music_combat.ogg".to_string(),
+            "audio/music_game_over.ogg".to_string(),
+        ];
+
+        let mut state_tracks = HashMap::new();
+        state_tracks.insert("MainMenu".to_string(), tracks[0].clone());
+        state_tracks.insert("Game".to_string(), tracks[1].clone());
+        state_tracks.insert("GameOver".to_string(), tracks[2].clone());
+
+        MusicTable {
+            tracks,
+            state_tracks,
+        }
+    }
+}
+
+/// Marks the currently playing background track so it can be faded out and
+/// replaced when the `AppState` changes.
+#[derive(Component)]
+pub struct MusicTrack;
+
+#[derive(Component)]
+pub struct FadingOut {
+    pub timer: Timer,
+}
+
+fn play_state_music(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    music_table: &MusicTable,
+    settings: &Settings,
+    existing_tracks: &Query<Entity, With<MusicTrack>>,
+    state_key: &str,
+) {
+    for entity in existing_tracks.iter() {
+        commands
+            .entity(entity)
+            .remove::<MusicTrack>()
+            .insert(FadingOut {
+                timer: Timer::from_seconds(MUSIC_FADE_SEC, TimerMode::Once),
+            });
+    }
+
+    let Some(track) = music_table.state_tracks.get(state_key) else {
+        return;
+    };
+
+    commands.spawn((
+        AudioBundle {
+            source: asset_server.load(track.clone()),
+            settings: PlaybackSettings::LOOP.with_volume(settings.music_gain()),
+        },
+        MusicTrack,
+    ));
+}
+
+pub fn play_menu_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    music_table: Res<MusicTable>,
+    settings: Res<Settings>,
+    existing_tracks: Query<Entity, With<MusicTrack>>,
+) {
+    play_state_music(
+        &mut commands,
+        &asset_server,
+        &music_table,
+        &settings,
+        &existing_tracks,
+        "MainMenu",
+    );
+}
+
+pub fn play_game_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    music_table: Res<MusicTable>,
+    settings: Res<Settings>,
+    existing_tracks: Query<Entity, With<MusicTrack>>,
+) {
+    play_state_music(
+        &mut commands,
+        &asset_server,
+        &music_table,
+        &settings,
+        &existing_tracks,
+        "Game",
+    );
+}
+
+pub fn play_game_over_music(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    music_table: Res<MusicTable>,
+    settings: Res<Settings>,
+    existing_tracks: Query<Entity, With<MusicTrack>>,
+) {
+    play_state_music(
+        &mut commands,
+        &asset_server,
+        &music_table,
+        &settings,
+        &existing_tracks,
+        "GameOver",
+    );
+}
+
+pub fn fade_out_music(
+    mut commands: Commands,
+    mut query: Query<(Entity, &AudioSink, &mut FadingOut)>,
+    time: Res<Time>,
+) {
+    for (entity, sink, mut fading) in query.iter_mut() {
+        fading.timer.tick(time.delta());
+        sink.set_volume(fading.timer.percent_left());
+
+        if fading.timer.finished() {
+            sink.stop();
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn pause_game_music(music_query: Query<&AudioSink, (With<MusicTrack>, Without<FadingOut>)>) {
+    for sink in music_query.iter() {
+        sink.pause();
+    }
+}
+
+pub fn resume_game_music(music_query: Query<&AudioSink, (With<MusicTrack>, Without<FadingOut>)>) {
+    for sink in music_query.iter() {
+        sink.play();
+    }
+}
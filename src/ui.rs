@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::health::Lives;
+use crate::settings::Settings;
+use crate::Score;
+
+const HUD_FONT: &str = "fonts/kenvector_future.ttf";
+
+fn hud_text_style(font: Handle<Font>, font_size: f32) -> TextStyle {
+    TextStyle {
+        font,
+        font_size,
+        color: Color::WHITE,
+    }
+}
+
+#[derive(Component)]
+pub struct ScoreboardUi;
+
+#[derive(Component)]
+pub struct ScoreboardText;
+
+pub fn spawn_scoreboard(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                ..default()
+            },
+            ScoreboardUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    hud_text_style(asset_server.load(HUD_FONT), 28.0),
+                ),
+                ScoreboardText,
+            ));
+        });
+}
+
+pub fn despawn_scoreboard(mut commands: Commands, scoreboard_query: Query<Entity, With<ScoreboardUi>>) {
+    for entity in scoreboard_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub fn update_scoreboard(
+    score: Res<Score>,
+    lives: Res<Lives>,
+    settings: Res<Settings>,
+    mut text_query: Query<&mut Text, With<ScoreboardText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    text.sections[0].value = format!(
+        "Score: {}\nLives: {}\nHigh Score: {}",
+        score.value, lives.current, settings.high_score
+    );
+}
+
+#[derive(Component)]
+pub struct MainMenuUi;
+
+pub fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            MainMenuUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Press G to start",
+                hud_text_style(asset_server.load(HUD_FONT), 48.0),
+            ));
+        });
+}
+
+pub fn despawn_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenuUi>>) {
+    for entity in menu_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[derive(Component)]
+pub struct PausedOverlayUi;
+
+pub fn spawn_paused_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BackgroundColor(Color::rgba(0.0, 0.0, 0.0, 0.6)),
+                ..default()
+            },
+            PausedOverlayUi,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "PAUSED",
+                hud_text_style(asset_server.load(HUD_FONT), 48.0),
+            ));
+        });
+}
+
+pub fn despawn_paused_overlay(
+    mut commands: Commands,
+    overlay_query: Query<Entity, With<PausedOverlayUi>>,
+) {
+    for entity in overlay_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}